@@ -0,0 +1,177 @@
+//! A buffered variant of `IoSlice` that implements `std::io::BufRead` without ever reading past
+//! its own slice boundary.
+
+use {
+    crate::IoSlice,
+    std::io::BufRead,
+    std::io::Read,
+    std::io::Seek,
+    std::io::SeekFrom,
+};
+
+
+
+/// A slice into some object, buffered internally so it can offer `std::io::BufRead` (`read_line`,
+/// `lines()`, `read_until`, byte-at-a-time parsing) without ever reading past the slice's own
+/// bound.
+///
+/// Plain `IoSlice` only exposes raw `Read`, which forces callers who want line-oriented access to
+/// wrap it in `std::io::BufReader` - but `BufReader` doesn't know about the slice's `remaining`
+/// bound and will happily over-read the underlying stream past the slice end. `BufIoSlice` fills
+/// its own buffer in bounded chunks so that can never happen.
+#[derive(Debug)]
+pub struct BufIoSlice<T> where T: Seek {
+    inner:    IoSlice<T>,
+    buffer:   Box<[u8]>,
+    filled:   usize,
+    consumed: usize,
+}
+
+impl<T> BufIoSlice<T> where T: Seek {
+    /// create a new buffered slice into a specific subset of `source`, with a fill buffer of
+    /// `capacity` bytes.
+    pub fn with_capacity(source: T, begin: u64, length: u64, capacity: usize) -> Result<BufIoSlice<T>, std::io::Error> {
+        let inner = IoSlice::new(source, begin, length)?;
+
+        Ok(BufIoSlice { inner, buffer: vec![0; capacity].into_boxed_slice(), filled: 0, consumed: 0 })
+    }
+
+    /// returns the total length of this slice.
+    pub fn len(&self) -> u64 {
+        self.inner.len().expect("BufIoSlice is always constructed with a bounded length")
+    }
+
+    /// returns the current position of this slice.
+    pub fn position(&self) -> u64 {
+        // `inner` has already read ahead to fill the buffer, so its position is `filled - consumed`
+        // bytes past what the caller has actually consumed.
+        self.inner.position() - (self.filled - self.consumed) as u64
+    }
+}
+
+impl<T> Seek for BufIoSlice<T> where T: Seek {
+    fn seek(&mut self, position: SeekFrom) -> Result<u64, std::io::Error> {
+        // :: `inner` has read ahead by whatever is still buffered, so a `Current` offset is taken
+        //    from our own logical position (see `position`) rather than from `inner`'s.
+        let position = match position {
+            SeekFrom::Current(value) => {
+                let unconsumed = (self.filled - self.consumed) as i64;
+
+                SeekFrom::Current(value.checked_sub(unconsumed).ok_or_else(|| std::io::Error::from(std::io::ErrorKind::InvalidInput))?)
+            }
+            other => other,
+        };
+
+        let new = self.inner.seek(position)?;
+
+        // :: seeking invalidates whatever we've buffered - discard it and let the next `fill_buf`
+        //    refill from the new position.
+        self.filled   = 0;
+        self.consumed = 0;
+
+        Ok(new)
+    }
+}
+
+impl<T> Read for BufIoSlice<T> where T: Read + Seek {
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, std::io::Error> {
+        // if nothing's buffered and the caller wants at least as much as our buffer could ever
+        // hold, skip buffering entirely and read straight into `buffer`.
+        if self.consumed == self.filled && buffer.len() >= self.buffer.len() {
+            return self.inner.read(buffer);
+        }
+
+        let available = self.fill_buf()?;
+        let amount     = std::cmp::min(available.len(), buffer.len());
+
+        buffer[..amount].copy_from_slice(&available[..amount]);
+        self.consume(amount);
+
+        Ok(amount)
+    }
+}
+
+impl<T> BufRead for BufIoSlice<T> where T: Read + Seek {
+    fn fill_buf(&mut self) -> Result<&[u8], std::io::Error> {
+        if self.consumed == self.filled {
+            let remaining = self.inner.remaining().expect("BufIoSlice is always constructed with a bounded length");
+            let request   = std::cmp::min(remaining, self.buffer.len() as u64) as usize;
+
+            self.filled   = self.inner.read(&mut self.buffer[..request])?;
+            self.consumed = 0;
+        }
+
+        Ok(&self.buffer[self.consumed..self.filled])
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.consumed = std::cmp::min(self.consumed + amount, self.filled);
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::io::Cursor,
+    };
+
+    #[test]
+    fn fill_buf_never_crosses_the_slice_boundary() {
+        let source    = Cursor::new(b"hello world, and then some trailing bytes".to_vec());
+        let mut slice = BufIoSlice::with_capacity(source, 0, 5, 64).unwrap();
+
+        assert_eq!(slice.fill_buf().unwrap(), b"hello");
+    }
+
+    #[test]
+    fn consume_advances_logical_position_without_re_reading() {
+        let source    = Cursor::new(b"hello world".to_vec());
+        let mut slice = BufIoSlice::with_capacity(source, 0, 11, 4).unwrap();
+
+        assert_eq!(slice.fill_buf().unwrap(), b"hell");
+        assert_eq!(slice.position(), 0);
+
+        slice.consume(2);
+        assert_eq!(slice.position(), 2);
+
+        let mut buffer = [0u8; 2];
+        slice.read_exact(&mut buffer).unwrap();
+
+        assert_eq!(&buffer, b"ll");
+        assert_eq!(slice.position(), 4);
+    }
+
+    #[test]
+    fn read_line_stops_at_the_slice_boundary() {
+        let source    = Cursor::new(b"line one\nline two\n".to_vec());
+        let mut slice = BufIoSlice::with_capacity(source, 0, 9, 64).unwrap();
+        let mut line  = String::new();
+
+        slice.read_line(&mut line).unwrap();
+
+        assert_eq!(line, "line one\n");
+
+        // the slice ends right after the first line, so nothing of "line two" is ever returned.
+        line.clear();
+
+        assert_eq!(slice.read_line(&mut line).unwrap(), 0);
+        assert_eq!(line, "");
+    }
+
+    #[test]
+    fn seeking_discards_buffered_content() {
+        let source    = Cursor::new(b"hello world".to_vec());
+        let mut slice = BufIoSlice::with_capacity(source, 0, 11, 64).unwrap();
+
+        slice.fill_buf().unwrap();
+        slice.seek(SeekFrom::Start(6)).unwrap();
+
+        let mut buffer = [0u8; 5];
+        slice.read_exact(&mut buffer).unwrap();
+
+        assert_eq!(&buffer, b"world");
+    }
+}
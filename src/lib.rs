@@ -46,6 +46,22 @@ use {
 
 
 
+mod buf;
+mod pos;
+
+#[cfg(feature = "mmap")]
+mod mmap;
+
+pub use buf::BufIoSlice;
+pub use pos::PosRead;
+pub use pos::PosSlice;
+pub use pos::PosWrite;
+
+#[cfg(feature = "mmap")]
+pub use mmap::MmapSlice;
+
+
+
 /// A slice, subset, or view into some object.
 ///
 /// `IoSlice` impls both `std::io::Read` and `std::io::Write` when the source implements them (and only one if the source
@@ -83,17 +99,17 @@ use {
 pub struct IoSlice<T> where T: Seek {
     // `IoSlice` supports slicing streams up to 9,000 PiB in size (`i64::max` bytes).
     //
-    // the value of `begin`, `length`, `remaining`, `begin + length` will never be greater than `std::max::i64`. these
-    // invariants are guarenteed by `IoSlice::new(...)`.
+    // the value of `begin`, `length` (when set), `pos`, `begin + length` will never be greater than `std::max::i64`.
+    // these invariants are guarenteed by `IoSlice::new(...)` / `IoSlice::from(...)`.
 
     underlying: T,
     begin:      u64,
-    length:     u64,
-    remaining:  u64,
+    length:     Option<u64>,
+    pos:        u64,
 }
 
 impl<T> IoSlice<T> where T: Seek {
-    /// create a new slice into a specific subset of `source`.
+    /// create a new slice into a specific, bounded subset of `source`.
     pub fn new(mut source: T, begin: u64, length: u64) -> Result<IoSlice<T>, std::io::Error> {
         // :: check invariants
         let i64_max = std::i64::MAX as u64;
@@ -108,20 +124,45 @@ impl<T> IoSlice<T> where T: Seek {
         let seek = SeekFrom::Start(begin);
 
         if source.seek(seek)? == begin {
-            let underlying = source;
-            let remaining  = length;
+            Ok(IoSlice { underlying: source, begin, length: Some(length), pos: 0 })
+        } else {
+            Err(std::io::ErrorKind::InvalidInput.into())
+        }
+    }
+
+    /// create an open-ended slice starting at `begin` and running to whatever `source`'s real end
+    /// turns out to be.
+    ///
+    /// unlike `new`, no length is required up front: reads simply pass through whatever the
+    /// underlying stream gives until it reports true EOF, and `SeekFrom::End` is resolved by
+    /// asking the stream for its real length. this mirrors representing unknown extent as absence
+    /// rather than a sentinel like `u64::MAX`.
+    pub fn from(mut source: T, begin: u64) -> Result<IoSlice<T>, std::io::Error> {
+        let i64_max = std::i64::MAX as u64;
+
+        if begin > i64_max {
+            return Err(std::io::ErrorKind::InvalidInput.into());
+        }
 
-            Ok(IoSlice { underlying, begin, length, remaining })
+        let seek = SeekFrom::Start(begin);
+
+        if source.seek(seek)? == begin {
+            Ok(IoSlice { underlying: source, begin, length: None, pos: 0 })
         } else {
             Err(std::io::ErrorKind::InvalidInput.into())
         }
     }
 
-    /// returns the total length of this io slice.
-    pub fn len(&self) -> u64 {
+    /// returns the total length of this io slice, or `None` if it's open-ended.
+    pub fn len(&self) -> Option<u64> {
         self.length
     }
 
+    /// returns the number of bytes left before this slice's bound, or `None` if it's open-ended.
+    pub fn remaining(&self) -> Option<u64> {
+        self.length.map(|length| length - self.pos)
+    }
+
     /// returns the current position of this slice.
     pub fn pos(&self) -> u64 {
         self.position()
@@ -129,37 +170,49 @@ impl<T> IoSlice<T> where T: Seek {
 
     /// returns the current position of this slice.
     pub fn position(&self) -> u64 {
-        self.length - self.remaining
+        self.pos
     }
 }
 
 impl<T> Seek for IoSlice<T> where T: Seek {
     fn seek(&mut self, position: SeekFrom) -> Result<u64, std::io::Error> {
-        // :: make sure that position(i64) is not more `std::i64::max`.
-        if match position { SeekFrom::Start(x) => x as u64, SeekFrom::Current(x) => x as u64, SeekFrom::End(x) => x as u64 } > std::i64::MAX as u64 {
-            return Err(std::io::ErrorKind::InvalidInput.into());
-        }
+        let invalid = || std::io::Error::from(std::io::ErrorKind::InvalidInput);
 
 
-        // :: then calculate the new stream offset.
-        let absolute = match position {
-            SeekFrom::Start(value)   => self.begin + value as u64,
-            SeekFrom::Current(value) => self.begin + self.length - self.remaining + value as u64,
-            SeekFrom::End(value)     => self.begin + self.length + value as u64,
+        // :: figure out where the end of this slice is. if it's bounded, this is known up front;
+        //    if it's open-ended, we have to ask the underlying stream how long it really is.
+        let end = match self.length {
+            Some(length) => self.begin + length,
+            None         => self.underlying.seek(SeekFrom::End(0))?,
         };
 
 
+        // :: then calculate the new stream offset. `Current`/`End` carry a signed `i64` - use
+        //    `checked_add_signed` rather than casting to `u64`, which would turn every negative
+        //    offset into a huge positive one and reject all relative backwards seeks.
+        let absolute = match position {
+            SeekFrom::Start(value)   => self.begin.checked_add(value),
+            SeekFrom::Current(value) => self.begin.checked_add(self.pos).and_then(|current| current.checked_add_signed(value)),
+            SeekFrom::End(value)     => end.checked_add_signed(value),
+        }.ok_or_else(invalid)?;
+
+        // :: make sure that position(i64) is not more than `std::i64::max`.
+        if absolute > std::i64::MAX as u64 {
+            return Err(invalid());
+        }
+
+
         // :: seek.
         //
         // if the new requested position is in bounds. seek to it, and make sure that the new position is the one we
         // requested.
-        if absolute >= self.begin && absolute <= self.begin + self.length {
+        if absolute >= self.begin && self.length.map_or(true, |length| absolute <= self.begin + length) {
             let seek = SeekFrom::Start(absolute);
 
             if self.underlying.seek(seek)? == absolute {
                 let new = absolute - self.begin;
 
-                self.remaining = self.length - new;
+                self.pos = new;
                 return Ok(new);
             }
 
@@ -174,58 +227,95 @@ impl<T> Seek for IoSlice<T> where T: Seek {
 impl<T> Read for IoSlice<T> where T: Read + Seek {
     fn read(&mut self, buffer: &mut [u8]) -> Result<usize, std::io::Error> {
         // `std::io::read::read()` can only read `usize::max` bytes at once.
-        let remaining   = std::cmp::min(self.remaining, std::usize::MAX as u64) as usize;
-        let request     = std::cmp::min(remaining, buffer.len());
-        let actual      = self.underlying.read(&mut buffer[..request])?;
+        let request = match self.length {
+            Some(length) => std::cmp::min(std::cmp::min(length - self.pos, std::usize::MAX as u64) as usize, buffer.len()),
+            None         => buffer.len(),
+        };
+
+        let actual = self.underlying.read(&mut buffer[..request])?;
 
-        self.remaining -= actual as u64;
+        self.pos += actual as u64;
 
         Ok(actual)
     }
 
     fn read_to_end(&mut self, buffer: &mut Vec<u8>) -> Result<usize, std::io::Error> {
-        if self.remaining > std::usize::MAX as u64 {
-            return Err(std::io::ErrorKind::InvalidInput.into())
-        }
+        match self.length {
+            Some(length) => {
+                let remaining = length - self.pos;
 
-        let length    = buffer.len();
-        let remaining = self.remaining as usize;
+                if remaining > std::usize::MAX as u64 {
+                    return Err(std::io::ErrorKind::InvalidInput.into())
+                }
 
-        buffer.reserve(remaining);
+                let start     = buffer.len();
+                let remaining = remaining as usize;
 
-        unsafe {
-            let pointer = buffer.as_mut_ptr().add(length);
-            let slice   = std::slice::from_raw_parts_mut(pointer, remaining);
+                buffer.reserve(remaining);
 
-            self.underlying.read_exact(slice)?;
-            buffer.set_len(length + remaining);
+                unsafe {
+                    let pointer = buffer.as_mut_ptr().add(start);
+                    let slice   = std::slice::from_raw_parts_mut(pointer, remaining);
 
-            self.remaining = 0;
-        }
+                    self.underlying.read_exact(slice)?;
+                    buffer.set_len(start + remaining);
+                }
+
+                self.pos = length;
+
+                Ok(remaining)
+            }
+
+            // we don't know how much is left until we hit true EOF, so there's nothing to
+            // pre-reserve - just read in chunks until the underlying stream runs dry.
+            None => {
+                let mut chunk = [0u8; 8192];
+                let mut total = 0;
+
+                loop {
+                    let actual = self.read(&mut chunk)?;
+
+                    if actual == 0 {
+                        break;
+                    }
+
+                    buffer.extend_from_slice(&chunk[..actual]);
+                    total += actual;
+                }
 
-        Ok(remaining)
+                Ok(total)
+            }
+        }
     }
 }
 
 impl<T> Write for IoSlice<T> where T: Write + Seek {
     fn write(&mut self, buffer: &[u8]) -> Result<usize, std::io::Error> {
-        if buffer.len() as u64 > self.remaining {
-            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        if let Some(length) = self.length {
+            if buffer.len() as u64 > length - self.pos {
+                return Err(std::io::ErrorKind::UnexpectedEof.into());
+            }
         }
 
         let actual = self.underlying.write(buffer)?;
 
-        self.remaining -= actual as u64;
+        self.pos += actual as u64;
 
         Ok(actual)
     }
 
     fn write_all(&mut self, buffer: &[u8]) -> Result<(), std::io::Error> {
-        if buffer.len() as u64 > self.remaining {
-            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        if let Some(length) = self.length {
+            if buffer.len() as u64 > length - self.pos {
+                return Err(std::io::ErrorKind::UnexpectedEof.into());
+            }
         }
 
-        self.underlying.write_all(buffer)
+        self.underlying.write_all(buffer)?;
+
+        self.pos += buffer.len() as u64;
+
+        Ok(())
     }
 
     fn flush(&mut self) -> Result<(), std::io::Error> {
@@ -239,7 +329,7 @@ impl<T> Clone for IoSlice<T> where T: Clone + Seek {
             underlying: self.underlying.clone(),
             begin:      self.begin,
             length:     self.length,
-            remaining:  self.remaining,
+            pos:        self.pos,
         }
     }
 }
@@ -250,7 +340,7 @@ impl<T> TryClone for IoSlice<T> where T: TryClone + Seek {
             underlying: self.underlying.try_clone()?,
             begin:      self.begin,
             length:     self.length,
-            remaining:  self.remaining,
+            pos:        self.pos,
         };
 
         Ok(clone)
@@ -269,3 +359,188 @@ impl TryClone for File {
         self.try_clone()
     }
 }
+
+
+
+impl<T> IoSlice<T> where T: Seek + TryClone {
+    /// carve out a smaller, bounded window within this slice, sharing the same underlying handle
+    /// via `TryClone`.
+    pub fn subslice(&self, offset: u64, length: u64) -> Result<IoSlice<T>, std::io::Error> {
+        let total   = self.length.ok_or_else(|| std::io::Error::from(std::io::ErrorKind::InvalidInput))?;
+        let invalid = || std::io::Error::from(std::io::ErrorKind::InvalidInput);
+
+        // :: use checked arithmetic throughout - `offset`/`length` are caller-supplied and must
+        //    not be allowed to overflow `u64` on the way to a bounds check.
+        offset.checked_add(length).filter(|end| *end <= total).ok_or_else(invalid)?;
+
+        let begin = self.begin.checked_add(offset).ok_or_else(invalid)?;
+
+        IoSlice::new(self.underlying.try_clone()?, begin, length)
+    }
+}
+
+
+
+/// An object-safe view of `IoSlice`'s core operations.
+///
+/// `IoSlice<T>` is generic over its source, so it can't be stored behind a trait object directly
+/// - `IoSliceDyn` erases the concrete `T` so callers can hold `Box<dyn IoSliceDyn>` and
+/// recursively window into a parsed container format (a disc image, an archive, ...) without
+/// propagating `T` everywhere.
+pub trait IoSliceDyn {
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, std::io::Error>;
+    fn write(&mut self, buffer: &[u8]) -> Result<usize, std::io::Error>;
+    fn seek(&mut self, position: SeekFrom) -> Result<u64, std::io::Error>;
+    fn len(&self) -> Option<u64>;
+    fn position(&self) -> u64;
+    fn subslice(&self, offset: u64, length: u64) -> Result<Box<dyn IoSliceDyn>, std::io::Error>;
+}
+
+impl<T> IoSliceDyn for IoSlice<T> where T: Read + Write + Seek + TryClone + 'static {
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, std::io::Error> {
+        Read::read(self, buffer)
+    }
+
+    fn write(&mut self, buffer: &[u8]) -> Result<usize, std::io::Error> {
+        Write::write(self, buffer)
+    }
+
+    fn seek(&mut self, position: SeekFrom) -> Result<u64, std::io::Error> {
+        Seek::seek(self, position)
+    }
+
+    fn len(&self) -> Option<u64> {
+        IoSlice::len(self)
+    }
+
+    fn position(&self) -> u64 {
+        IoSlice::position(self)
+    }
+
+    fn subslice(&self, offset: u64, length: u64) -> Result<Box<dyn IoSliceDyn>, std::io::Error> {
+        Ok(Box::new(IoSlice::subslice(self, offset, length)?))
+    }
+}
+
+
+
+#[cfg(feature = "mmap")]
+impl<T> IoSlice<T> where T: Seek + std::borrow::Borrow<File> {
+    /// map this slice's region directly into memory, avoiding a copy. only available for
+    /// bounded slices - an open-ended slice (built with `IoSlice::from`) has no fixed length to
+    /// map.
+    pub fn as_mmap(&self) -> Result<MmapSlice, std::io::Error> {
+        let length = self.length.ok_or_else(|| std::io::Error::from(std::io::ErrorKind::InvalidInput))?;
+
+        mmap::MmapSlice::new(self.underlying.borrow(), self.begin, length)
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::io::Cursor,
+    };
+
+    #[test]
+    fn bounded_len_reports_the_explicit_length() {
+        let source = Cursor::new(vec![0u8; 32]);
+        let slice  = IoSlice::new(source, 4, 10).unwrap();
+
+        assert_eq!(slice.len(), Some(10));
+    }
+
+    #[test]
+    fn open_ended_len_is_none() {
+        let source = Cursor::new(vec![0u8; 32]);
+        let slice  = IoSlice::from(source, 4).unwrap();
+
+        assert_eq!(slice.len(), None);
+    }
+
+    #[test]
+    fn open_ended_read_passes_through_to_the_real_eof() {
+        let source    = Cursor::new(b"hello world".to_vec());
+        let mut slice = IoSlice::from(source, 6).unwrap();
+        let mut buffer = [0u8; 16];
+
+        let actual = slice.read(&mut buffer).unwrap();
+
+        assert_eq!(&buffer[..actual], b"world");
+        assert_eq!(slice.read(&mut buffer).unwrap(), 0);
+    }
+
+    #[test]
+    fn open_ended_read_to_end_reads_everything_remaining() {
+        let source    = Cursor::new(b"hello world".to_vec());
+        let mut slice = IoSlice::from(source, 6).unwrap();
+        let mut buffer = Vec::new();
+
+        slice.read_to_end(&mut buffer).unwrap();
+
+        assert_eq!(buffer, b"world");
+    }
+
+    #[test]
+    fn open_ended_seek_from_end_resolves_against_the_real_stream() {
+        let source    = Cursor::new(b"hello world".to_vec());
+        let mut slice = IoSlice::from(source, 0).unwrap();
+
+        assert_eq!(slice.seek(SeekFrom::End(-5)).unwrap(), 6);
+
+        let mut buffer = [0u8; 5];
+        slice.read_exact(&mut buffer).unwrap();
+
+        assert_eq!(&buffer, b"world");
+    }
+
+    static COUNTER: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+    fn temp_file(contents: &[u8]) -> File {
+        let id   = COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("slice-lib-test-{}-{}", std::process::id(), id));
+
+        let mut file = std::fs::OpenOptions::new().create(true).truncate(true).read(true).write(true).open(&path).unwrap();
+
+        file.write_all(contents).unwrap();
+
+        file
+    }
+
+    #[test]
+    fn subslice_carves_a_smaller_bounded_window_sharing_the_handle() {
+        let file      = temp_file(b"hello world");
+        let slice     = IoSlice::new(file, 0, 11).unwrap();
+        let mut sub   = slice.subslice(6, 5).unwrap();
+        let mut buffer = [0u8; 5];
+
+        sub.read_exact(&mut buffer).unwrap();
+
+        assert_eq!(&buffer, b"world");
+    }
+
+    #[test]
+    fn subslice_rejects_out_of_bounds_and_overflowing_offsets() {
+        let file  = temp_file(b"hello world");
+        let slice = IoSlice::new(file, 0, 11).unwrap();
+
+        assert!(slice.subslice(6, 6).is_err());
+        assert!(slice.subslice(u64::MAX, 1).is_err());
+    }
+
+    #[test]
+    fn ioslicedyn_erases_the_concrete_source_and_recurses_via_subslice() {
+        let file  = temp_file(b"hello world");
+        let boxed: Box<dyn IoSliceDyn> = Box::new(IoSlice::new(file, 0, 11).unwrap());
+
+        let mut sub = boxed.subslice(6, 5).unwrap();
+        let mut buffer = [0u8; 5];
+
+        assert_eq!(sub.read(&mut buffer).unwrap(), 5);
+        assert_eq!(&buffer, b"world");
+        assert_eq!(sub.len(), Some(5));
+    }
+}
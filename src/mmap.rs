@@ -0,0 +1,135 @@
+//! Zero-copy, memory-mapped access to an `IoSlice`'s region. Gated behind the `mmap` feature
+//! since it pulls in `libc` and only makes sense for file-backed sources on platforms that
+//! support `mmap`.
+
+use {
+    std::fs::File,
+    std::ops::Deref,
+    std::os::unix::io::AsRawFd,
+};
+
+
+
+/// A memory-mapped view of `[begin, begin + length)` of some file, `Deref`ing to `&[u8]`.
+///
+/// The OS only maps whole pages, so the mapping itself starts at `begin` rounded down to the page
+/// size - but the `&[u8]` this hands back is re-sliced to start exactly at `begin` and is exactly
+/// `length` bytes long, so callers never see the rounding.
+pub struct MmapSlice {
+    pointer: *mut libc::c_void,
+    map_len: usize,
+    offset:  usize,
+    length:  usize,
+}
+
+impl MmapSlice {
+    pub(crate) fn new(file: &File, begin: u64, length: u64) -> Result<MmapSlice, std::io::Error> {
+        // `mmap(len=0)` fails with `EINVAL` - an empty region has nothing to map, so hand back
+        // an empty slice directly instead of calling into the OS. `slice::from_raw_parts`
+        // requires a non-null, aligned pointer even for a zero-length slice, so use a dangling
+        // one rather than null.
+        if length == 0 {
+            let pointer = std::ptr::NonNull::<u8>::dangling().as_ptr() as *mut libc::c_void;
+
+            return Ok(MmapSlice { pointer, map_len: 0, offset: 0, length: 0 });
+        }
+
+        let page_size     = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as u64;
+        let aligned_begin = (begin / page_size) * page_size;
+        let offset        = (begin - aligned_begin) as usize;
+        let map_len       = (offset as u64 + length) as usize;
+
+        let pointer = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                map_len,
+                libc::PROT_READ,
+                libc::MAP_PRIVATE,
+                file.as_raw_fd(),
+                aligned_begin as libc::off_t,
+            )
+        };
+
+        if pointer == libc::MAP_FAILED {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(MmapSlice { pointer, map_len, offset, length: length as usize })
+    }
+}
+
+impl Deref for MmapSlice {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts((self.pointer as *const u8).add(self.offset), self.length) }
+    }
+}
+
+impl Drop for MmapSlice {
+    fn drop(&mut self) {
+        if self.map_len > 0 {
+            unsafe { libc::munmap(self.pointer, self.map_len); }
+        }
+    }
+}
+
+// the mapping is read-only and never mutated through `MmapSlice` itself, so it's sound to share
+// the mapped pages across threads.
+unsafe impl Send for MmapSlice {}
+unsafe impl Sync for MmapSlice {}
+
+
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::io::Write,
+        std::sync::atomic::AtomicUsize,
+        std::sync::atomic::Ordering,
+    };
+
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    fn temp_file(contents: &[u8]) -> File {
+        let id   = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("slice-mmap-test-{}-{}", std::process::id(), id));
+
+        let mut file = std::fs::OpenOptions::new().create(true).truncate(true).read(true).write(true).open(&path).unwrap();
+
+        file.write_all(contents).unwrap();
+
+        file
+    }
+
+    #[test]
+    fn maps_exactly_the_requested_region() {
+        let file = temp_file(b"hello world");
+        let mmap = MmapSlice::new(&file, 6, 5).unwrap();
+
+        assert_eq!(&*mmap, b"world");
+    }
+
+    #[test]
+    fn rounds_down_to_a_page_boundary_but_exposes_only_the_requested_bytes() {
+        let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as u64;
+        let contents: Vec<u8> = (0..page_size + 32).map(|value| (value % 251) as u8).collect();
+        let file = temp_file(&contents);
+
+        let begin  = page_size - 4;
+        let length = 16;
+        let mmap   = MmapSlice::new(&file, begin, length).unwrap();
+
+        assert_eq!(mmap.len(), length as usize);
+        assert_eq!(&*mmap, &contents[begin as usize .. (begin + length) as usize]);
+    }
+
+    #[test]
+    fn zero_length_maps_nothing_without_calling_into_the_os() {
+        let file = temp_file(b"hello world");
+        let mmap = MmapSlice::new(&file, 0, 0).unwrap();
+
+        assert_eq!(&*mmap, &[] as &[u8]);
+    }
+}
@@ -0,0 +1,259 @@
+//! Positioned I/O: reads and writes that target an explicit offset instead of advancing a
+//! shared cursor, so several views into one descriptor can be used concurrently without
+//! fighting over its position.
+
+use {
+    std::borrow::Borrow,
+    std::fs::File,
+    std::io::Seek,
+    std::io::SeekFrom,
+};
+
+
+
+/// Positioned reads: read from a fixed `offset` rather than from the source's own cursor. on unix
+/// the cursor is left untouched; on windows (`seek_read`) it is moved to the end of the read.
+pub trait PosRead {
+    fn read_at(&self, buffer: &mut [u8], offset: u64) -> std::io::Result<usize>;
+}
+
+/// Positioned writes: write at a fixed `offset` rather than at the source's own cursor. on unix
+/// the cursor is left untouched; on windows (`seek_write`) it is moved to the end of the write.
+pub trait PosWrite {
+    fn write_at(&self, buffer: &[u8], offset: u64) -> std::io::Result<usize>;
+}
+
+fn file_read_at(file: &File, buffer: &mut [u8], offset: u64) -> std::io::Result<usize> {
+    #[cfg(unix)]
+    { std::os::unix::fs::FileExt::read_at(file, buffer, offset) }
+
+    #[cfg(windows)]
+    { std::os::windows::fs::FileExt::seek_read(file, buffer, offset) }
+}
+
+fn file_write_at(file: &File, buffer: &[u8], offset: u64) -> std::io::Result<usize> {
+    #[cfg(unix)]
+    { std::os::unix::fs::FileExt::write_at(file, buffer, offset) }
+
+    #[cfg(windows)]
+    { std::os::windows::fs::FileExt::seek_write(file, buffer, offset) }
+}
+
+// :: blanket impls over `Borrow<File>` rather than a direct impl on `File` - `File: Borrow<File>`
+//    already holds (the standard library's reflexive `impl<T> Borrow<T> for T`), so this single
+//    impl covers `File` as well as `&File`, `Arc<File>`, `Box<File>`, and so on.
+impl<P> PosRead for P where P: Borrow<File> {
+    fn read_at(&self, buffer: &mut [u8], offset: u64) -> std::io::Result<usize> {
+        file_read_at(self.borrow(), buffer, offset)
+    }
+}
+
+impl<P> PosWrite for P where P: Borrow<File> {
+    fn write_at(&self, buffer: &[u8], offset: u64) -> std::io::Result<usize> {
+        file_write_at(self.borrow(), buffer, offset)
+    }
+}
+
+
+
+/// A slice, subset, or view into some positioned source, backed by `read_at`/`write_at` rather
+/// than a shared cursor.
+///
+/// Unlike `IoSlice`, `PosSlice` never seeks the underlying source: every `read`/`write` is
+/// translated into a positioned call at `begin + pos`, and `Seek` is pure arithmetic on `pos`
+/// with no syscall. This means `PosSlice<Arc<File>>` can be cloned cheaply and read concurrently
+/// from many threads over disjoint regions of one descriptor.
+#[derive(Debug)]
+pub struct PosSlice<P> {
+    // `PosSlice` supports slicing streams up to 9,000 PiB in size (`i64::max` bytes), the same
+    // invariant `IoSlice` holds: `begin`, `length`, `begin + length` never exceed `std::i64::max`.
+
+    underlying: P,
+    begin:      u64,
+    length:     u64,
+    pos:        u64,
+}
+
+impl<P> PosSlice<P> {
+    /// create a new positioned slice into a specific subset of `source`.
+    pub fn new(source: P, begin: u64, length: u64) -> Result<PosSlice<P>, std::io::Error> {
+        let i64_max = std::i64::MAX as u64;
+
+        if begin > i64_max || length > i64_max || begin + length > i64_max {
+            return Err(std::io::ErrorKind::InvalidInput.into());
+        }
+
+        Ok(PosSlice { underlying: source, begin, length, pos: 0 })
+    }
+
+    /// returns the total length of this slice.
+    pub fn len(&self) -> u64 {
+        self.length
+    }
+
+    /// returns the current position of this slice.
+    pub fn pos(&self) -> u64 {
+        self.position()
+    }
+
+    /// returns the current position of this slice.
+    pub fn position(&self) -> u64 {
+        self.pos
+    }
+}
+
+impl<P> Seek for PosSlice<P> {
+    fn seek(&mut self, position: SeekFrom) -> Result<u64, std::io::Error> {
+        let invalid = || std::io::Error::from(std::io::ErrorKind::InvalidInput);
+
+        // :: calculate the new stream offset. `Current`/`End` carry a signed `i64` - use
+        //    `checked_add_signed` rather than casting to `u64`, which would turn every negative
+        //    offset into a huge positive one.
+        let absolute = match position {
+            SeekFrom::Start(value)   => Some(value),
+            SeekFrom::Current(value) => self.pos.checked_add_signed(value),
+            SeekFrom::End(value)     => self.length.checked_add_signed(value),
+        }.ok_or_else(invalid)?;
+
+        // :: make sure that position(i64) is not more than `std::i64::max`.
+        if absolute > std::i64::MAX as u64 {
+            return Err(invalid());
+        }
+
+        if absolute <= self.length {
+            self.pos = absolute;
+            return Ok(absolute);
+        }
+
+        // the new requested position is out of bounds, return eof. we don't allow seeking out of bounds.
+        Err(std::io::ErrorKind::UnexpectedEof.into())
+    }
+}
+
+impl<P> std::io::Read for PosSlice<P> where P: PosRead {
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, std::io::Error> {
+        let remaining = std::cmp::min(self.length - self.pos, std::usize::MAX as u64) as usize;
+        let request   = std::cmp::min(remaining, buffer.len());
+        let actual    = self.underlying.read_at(&mut buffer[..request], self.begin + self.pos)?;
+
+        self.pos += actual as u64;
+
+        Ok(actual)
+    }
+}
+
+impl<P> std::io::Write for PosSlice<P> where P: PosWrite {
+    fn write(&mut self, buffer: &[u8]) -> Result<usize, std::io::Error> {
+        if buffer.len() as u64 > self.length - self.pos {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+
+        let actual = self.underlying.write_at(buffer, self.begin + self.pos)?;
+
+        self.pos += actual as u64;
+
+        Ok(actual)
+    }
+
+    fn flush(&mut self) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+}
+
+impl<P> Clone for PosSlice<P> where P: Clone {
+    fn clone(&self) -> PosSlice<P> {
+        PosSlice {
+            underlying: self.underlying.clone(),
+            begin:      self.begin,
+            length:     self.length,
+            pos:        self.pos,
+        }
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::io::Read,
+        std::io::Write,
+        std::sync::Arc,
+        std::sync::atomic::AtomicUsize,
+        std::sync::atomic::Ordering,
+    };
+
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    fn temp_file(contents: &[u8]) -> File {
+        let id   = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("slice-pos-test-{}-{}", std::process::id(), id));
+
+        let mut file = std::fs::OpenOptions::new().create(true).truncate(true).read(true).write(true).open(&path).unwrap();
+
+        file.write_all(contents).unwrap();
+        file.seek(SeekFrom::Start(0)).unwrap();
+
+        file
+    }
+
+    #[test]
+    fn reads_from_the_requested_offset() {
+        let file    = temp_file(b"hello world");
+        let mut slice = PosSlice::new(file, 6, 5).unwrap();
+        let mut buffer = [0u8; 5];
+
+        assert_eq!(slice.read(&mut buffer).unwrap(), 5);
+        assert_eq!(&buffer, b"world");
+    }
+
+    #[test]
+    fn writes_at_the_requested_offset() {
+        let file = temp_file(b"hello world");
+        let mut slice = PosSlice::new(file, 0, 5).unwrap();
+
+        slice.write(b"HELLO").unwrap();
+        slice.seek(SeekFrom::Start(0)).unwrap();
+
+        let mut buffer = [0u8; 5];
+        slice.read(&mut buffer).unwrap();
+
+        assert_eq!(&buffer, b"HELLO");
+    }
+
+    #[test]
+    fn seek_is_pure_arithmetic_and_rejects_out_of_bounds() {
+        let file = temp_file(b"hello world");
+        let mut slice = PosSlice::new(file, 0, 11).unwrap();
+
+        assert_eq!(slice.seek(SeekFrom::End(0)).unwrap(), 11);
+        assert_eq!(slice.seek(SeekFrom::Current(-5)).unwrap(), 6);
+        assert!(slice.seek(SeekFrom::Start(12)).is_err());
+    }
+
+    #[test]
+    fn clone_shares_the_underlying_handle_but_not_the_position() {
+        let file = Arc::new(temp_file(b"hello world"));
+        let mut original = PosSlice::new(file, 0, 11).unwrap();
+
+        original.seek(SeekFrom::Start(3)).unwrap();
+        let mut clone = original.clone();
+
+        assert_eq!(clone.position(), 3);
+        assert_eq!(original.position(), 3);
+
+        // reading through the clone advances only the clone, and both read the same file.
+        let mut buffer = [0u8; 4];
+        clone.read_exact(&mut buffer).unwrap();
+
+        assert_eq!(&buffer, b"lo w");
+        assert_eq!(clone.position(), 7);
+        assert_eq!(original.position(), 3);
+
+        let mut buffer = [0u8; 2];
+        original.read_exact(&mut buffer).unwrap();
+
+        assert_eq!(&buffer, b"lo");
+    }
+}